@@ -3,7 +3,7 @@ use core::str;
 use serde::{Deserialize, Serialize};
 use validator::Validate;
 
-use crate::models::{User, UserRole};
+use crate::models::{Credential, CredentialType, Permission, Scope, User, UserRole};
 
 #[derive(Validate, Debug, Default, Clone, Serialize, Deserialize)]
 pub struct RegisterUserDto {
@@ -34,6 +34,18 @@ pub struct RegisterUserDto {
     pub password_confirm: String,
 }
 
+impl RegisterUserDto {
+    // 登録情報を Credential テーブルへ insert_credentials 経由で挿入するための
+    // (credential_type, credential) の組に変換する。email/password は User の
+    // 固定カラムとは別に、この組を介して Credential としても保存される
+    pub fn to_credentials(&self) -> Vec<(CredentialType, String)> {
+        vec![
+            (CredentialType::Email, self.email.clone()),
+            (CredentialType::Password, self.password.clone()),
+        ]
+    }
+}
+
 #[derive(Validate, Debug, Default, Clone, Serialize, Deserialize)]
 pub struct LoginUserDto {
     // ログイン用のメールアドレス検証（必須・形式チェック）
@@ -50,6 +62,21 @@ pub struct LoginUserDto {
     pub password: String,
 }
 
+impl LoginUserDto {
+    // fetch_user_credentials で取得した一覧から、このDTOのemailと一致する
+    // Email Credential を探す（メールアドレスの照合はCredentialテーブル越しに行う）
+    pub fn find_matching_email_credential<'a>(&self, credentials: &'a [Credential]) -> Option<&'a Credential> {
+        credentials
+            .iter()
+            .find(|c| c.credential_type == CredentialType::Email && c.credential == self.email)
+    }
+
+    // 同じユーザーのCredential一覧からPassword Credentialを探す（検証はmodels::verify_passwordで行う）
+    pub fn find_password_credential<'a>(&self, credentials: &'a [Credential]) -> Option<&'a Credential> {
+        credentials.iter().find(|c| c.credential_type == CredentialType::Password)
+    }
+}
+
 #[derive(Serialize, Deserialize, Validate)]
 pub struct RequestQueryDto {
     // pageは1以上の数値が必須
@@ -121,8 +148,33 @@ pub struct UserListResponseDto {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UserLoginResponseDto {
     // ログイン時のレスポンスDTO (tokenを付与)
-    pub status: String, 
+    pub status: String,
+    pub token: String,
+}
+
+#[derive(Validate, Debug, Clone, Serialize, Deserialize)]
+pub struct ScopedTokenRequestDto {
+    // サービス間・CIジョブ向けに、フルアクセスではなく限定的なスコープのトークンを要求するDTO
+    #[validate(length(min = 1, message = "At least one scope is required"))]
+    pub scopes: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScopedTokenResponseDto {
+    // 発行したスコープ付きトークンと、そこに実際に載せたスコープ一覧を返すDTO
+    pub status: String,
     pub token: String,
+    pub scopes: Vec<String>,
+}
+
+impl ScopedTokenResponseDto {
+    pub fn new(token: String, scopes: &[Scope]) -> Self {
+        ScopedTokenResponseDto {
+            status: "success".to_owned(),
+            token,
+            scopes: scopes.iter().map(|s| s.to_str().to_owned()).collect(),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -154,6 +206,58 @@ fn validate_user_role(role: &UserRole) -> Result<(), validator::ValidationError>
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct RoleAssignDto {
+    // 割り当て先ユーザーのID
+    #[validate(length(min = 1, message = "User id is required"))]
+    pub user_id: String,
+
+    // 割り当てるロール名（権限サブシステム側のRoleを指す）
+    #[validate(length(min = 1, message = "Role name is required"))]
+    pub role_name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CredentialResponseDto {
+    // ユーザーが保持する認証情報の一覧を返すDTO（認証情報の値自体は含めない）
+    pub credential_type: String,
+    pub validated: bool,
+}
+
+impl CredentialResponseDto {
+    // 単一のCredentialをCredentialResponseDtoに変換するヘルパーメソッド
+    pub fn from_credential(credential: &Credential) -> Self {
+        CredentialResponseDto {
+            credential_type: credential.credential_type.to_str().to_owned(),
+            validated: credential.validated,
+        }
+    }
+
+    // 複数のCredentialをまとめてCredentialResponseDtoのベクターに変換するヘルパーメソッド
+    pub fn from_credentials(credentials: &[Credential]) -> Vec<CredentialResponseDto> {
+        credentials.iter().map(CredentialResponseDto::from_credential).collect()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RoleResponseDto {
+    // ロール情報と、そのロールが持つ権限の一覧を返すDTO
+    pub name: String,
+    pub description: String,
+    pub permissions: Vec<String>,
+}
+
+impl RoleResponseDto {
+    // 権限の集合からレスポンスDTOを組み立てるヘルパー
+    pub fn from_permissions(name: &str, description: &str, permissions: &[Permission]) -> Self {
+        RoleResponseDto {
+            name: name.to_owned(),
+            description: description.to_owned(),
+            permissions: permissions.iter().map(|p| p.to_str().to_owned()).collect(),
+        }
+    }
+}
+
 #[derive(Debug, Validate, Default, Clone, Serialize, Deserialize)]
 pub struct UserPasswordUpdateDto {
     // 新しいパスワードは6文字以上
@@ -190,6 +294,48 @@ pub struct ForgotPasswordRequestDto {
     pub email: String,
 }
 
+#[derive(Validate, Debug, Clone, Serialize, Deserialize)]
+pub struct PreloginRequestDto {
+    // クライアント側でログイン用ハッシュを計算する前に、KDFパラメータを問い合わせるためのメール
+    #[validate(length(min = 1, message = "Email is required"), email(message = "Email is invalid"))]
+    pub email: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PreloginResponseDto {
+    // クライアントがマスターパスワードを鍵導出する際に使うKDFの種類と反復回数
+    pub kdf: String,
+    pub kdf_iterations: u32,
+}
+
+#[derive(Validate, Debug, Clone, Serialize, Deserialize)]
+pub struct PasswordHintRequestDto {
+    // 保存されているパスワードヒントをメールで送るためのDTO
+    #[validate(length(min = 1, message = "Email is required"), email(message = "Email is invalid"))]
+    pub email: String,
+}
+
+#[derive(Validate, Debug, Clone, Serialize, Deserialize)]
+pub struct DeleteAccountRequestDto {
+    // 現在のパスワード。誤操作による削除を防ぐため必須（他のパスワード系DTOと同じ6文字以上の検証）
+    #[validate(length(min = 6, message = "Password must be at least 6 characters"))]
+    pub password: String,
+}
+
+#[derive(Validate, Debug, Clone, Serialize, Deserialize)]
+pub struct DeleteAccountConfirmDto {
+    // メールで送られた削除確認用トークン
+    #[validate(length(min = 1, message = "Token is required"))]
+    pub token: String,
+}
+
+#[derive(Validate, Debug, Clone, Serialize, Deserialize)]
+pub struct RecoverAccountDeletionDto {
+    // 有効期限内であれば、保留中のアカウント削除を取り消すためのトークン
+    #[validate(length(min = 1, message = "Token is required"))]
+    pub token: String,
+}
+
 #[derive(Debug, Serialize, Deserialize, Validate, Clone)]
 pub struct ResetPasswordRequestDto {
     // パスワードリセット用トークン必須
@@ -31,6 +31,12 @@ pub enum ErrorMessage {
     TokenNotProvided,
     PermissionDenied,
     UserNotAuthenticated,
+    MissingPermission(String),
+    RoleNotFound,
+    UnsupportedHashFormat,
+    DeletionTokenExpired,
+    InvalidDeletionToken,
+    InsufficientScope(String),
 }
 
 impl ToString for ErrorMessage {
@@ -53,6 +59,12 @@ impl ErrorMessage {
             ErrorMessage::TokenNotProvided => "Token not provided".to_owned(),
             ErrorMessage::PermissionDenied => "Permission denied".to_owned(),
             ErrorMessage::UserNotAuthenticated => "User not authenticated".to_owned(),
+            ErrorMessage::MissingPermission(permission) => format!("Missing required permission: {}", permission),
+            ErrorMessage::RoleNotFound => "Role not found".to_owned(),
+            ErrorMessage::UnsupportedHashFormat => "Unsupported password hash format".to_owned(),
+            ErrorMessage::DeletionTokenExpired => "Account deletion token has expired".to_owned(),
+            ErrorMessage::InvalidDeletionToken => "Invalid account deletion token".to_owned(),
+            ErrorMessage::InsufficientScope(scope) => format!("Token is missing required scope: {}", scope),
         }
     }
 }
\ No newline at end of file
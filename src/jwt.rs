@@ -0,0 +1,74 @@
+// JWTの生成・検証を行うモジュール。Configに設定された署名アルゴリズム・鍵を使う
+
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::{decode, encode, Header, Validation};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::error::ErrorMessage;
+use crate::models::{Scope, TokenInfo};
+
+// JWTのペイロードに載せる最小限のクレーム（sub: ユーザーID、iat/exp: 発行・失効時刻）
+#[derive(Debug, Serialize, Deserialize)]
+struct TokenClaims {
+    sub: String,
+    iat: usize,
+    exp: usize,
+}
+
+// ユーザーID (sub) から、Configで設定されたアルゴリズムと鍵で署名したフルアクセスのJWTを発行する
+pub fn create_token(user_id: &str, config: &Config) -> Result<String, ErrorMessage> {
+    let now = Utc::now();
+    let claims = TokenClaims {
+        sub: user_id.to_owned(),
+        iat: now.timestamp() as usize,
+        exp: (now + Duration::seconds(config.jwt_maxage)).timestamp() as usize,
+    };
+
+    let header = Header::new(config.jwt_algorithm);
+    encode(&header, &claims, &config.jwt_encoding_key).map_err(|_| ErrorMessage::ServerError)
+}
+
+// JWTを検証し、Configで設定されたアルゴリズムと鍵でsubject (ユーザーID) を取り出す
+pub fn decode_token(token: &str, config: &Config) -> Result<String, ErrorMessage> {
+    let validation = Validation::new(config.jwt_algorithm);
+    decode::<TokenClaims>(token, &config.jwt_decoding_key, &validation)
+        .map(|data| data.claims.sub)
+        .map_err(|_| ErrorMessage::InvalidToken)
+}
+
+// スコープ付きJWTのペイロード。フルアクセスのTokenClaimsに scopes クレームを加えたもの
+#[derive(Debug, Serialize, Deserialize)]
+struct ScopedTokenClaims {
+    sub: String,
+    scopes: Vec<String>,
+    iat: usize,
+    exp: usize,
+}
+
+// サービス間・CIジョブ向けに、subject と限定的なスコープの集合を載せたJWTを発行する
+pub fn create_scoped_token(subject: &str, scopes: &[Scope], config: &Config) -> Result<String, ErrorMessage> {
+    let now = Utc::now();
+    let claims = ScopedTokenClaims {
+        sub: subject.to_owned(),
+        scopes: scopes.iter().map(|s| s.to_str().to_owned()).collect(),
+        iat: now.timestamp() as usize,
+        exp: (now + Duration::seconds(config.jwt_maxage)).timestamp() as usize,
+    };
+
+    let header = Header::new(config.jwt_algorithm);
+    encode(&header, &claims, &config.jwt_encoding_key).map_err(|_| ErrorMessage::ServerError)
+}
+
+// スコープ付きJWTを検証し、ルート側がスコープを判定できるようTokenInfoへ変換する。
+// 未知のスコープ文字列（将来のバージョンが発行したもの等）は無視する
+pub fn decode_scoped_token(token: &str, config: &Config) -> Result<TokenInfo, ErrorMessage> {
+    let validation = Validation::new(config.jwt_algorithm);
+    let data = decode::<ScopedTokenClaims>(token, &config.jwt_decoding_key, &validation)
+        .map_err(|_| ErrorMessage::InvalidToken)?;
+
+    let scopes = data.claims.scopes.iter().filter_map(|scope| scope.parse().ok()).collect();
+    let expires_at = DateTime::<Utc>::from_timestamp(data.claims.exp as i64, 0).unwrap_or_else(Utc::now);
+
+    Ok(TokenInfo { subject: data.claims.sub, scopes, expires_at })
+}
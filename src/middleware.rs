@@ -0,0 +1,49 @@
+// ルートに必要なスコープを強制するための axum extractor
+
+use axum::{
+    extract::FromRequestParts,
+    http::{header, request::Parts, StatusCode},
+    Json,
+};
+
+use crate::config::Config;
+use crate::error::{ErrorMessage, ErrorResponse};
+use crate::jwt::decode_scoped_token;
+use crate::models::{Scope, TokenInfo};
+
+// Authorization: Bearer <token> ヘッダーからスコープ付きJWTを取り出す extractor
+// ルートハンドラの引数に `ScopedToken(token_info): ScopedToken` のように書いて使う想定
+pub struct ScopedToken(pub TokenInfo);
+
+impl FromRequestParts<Config> for ScopedToken {
+    type Rejection = (StatusCode, Json<ErrorResponse>);
+
+    async fn from_request_parts(parts: &mut Parts, config: &Config) -> Result<Self, Self::Rejection> {
+        let token = parts
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or_else(|| error_response(StatusCode::UNAUTHORIZED, ErrorMessage::TokenNotProvided))?;
+
+        let token_info = decode_scoped_token(token, config).map_err(|err| error_response(StatusCode::UNAUTHORIZED, err))?;
+
+        Ok(ScopedToken(token_info))
+    }
+}
+
+impl ScopedToken {
+    // このトークンが指定したスコープを持っているか検証する。持たなければ403を返す
+    pub fn require(self, scope: Scope) -> Result<TokenInfo, (StatusCode, Json<ErrorResponse>)> {
+        if self.0.has_scope(scope) {
+            Ok(self.0)
+        } else {
+            Err(error_response(StatusCode::FORBIDDEN, ErrorMessage::InsufficientScope(scope.to_str().to_owned())))
+        }
+    }
+}
+
+fn error_response(status: StatusCode, error_message: ErrorMessage) -> (StatusCode, Json<ErrorResponse>) {
+    let body = ErrorResponse { status: "fail".to_owned(), message: error_message.to_string() };
+    (status, Json(body))
+}
@@ -1,6 +1,13 @@
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Algorithm as Argon2Algorithm, Argon2, Params as Argon2LibParams, Version as Argon2Version,
+};
 use chrono::prelude::*; // 日付や時刻の操作に必要な型やトレイト（例: DateTime, Utc）
+use scrypt::{Params as ScryptLibParams, Scrypt};
 use serde::{Deserialize, Serialize}; // 構造体や列挙型をJSONなどに変換（Serialize）、またはその逆に変換（Deserialize）
 
+use crate::error::ErrorMessage;
+
 // ユーザーの役割を表す列挙型（例: 管理者(Admin) または 一般ユーザー(User)）
 #[derive(Debug, Deserialize, Serialize, Clone, Copy, sqlx::Type, PartialEq)] // トレイトの自動実装
 #[sqlx(type_name = "user_role", rename_all = "lowercase")] // データベースでこの型をENUMとしてマッピングし、小文字で保存
@@ -20,6 +27,353 @@ impl UserRole {
     }
 }
 
+// 個々の操作に対応する権限を表す列挙型（role == Admin という二値判定に代わる細粒度の認可単位）
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, sqlx::Type, PartialEq, Eq, Hash)]
+#[sqlx(type_name = "permission", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Permission {
+    UserManagementRead,
+    UserManagementWrite,
+    UserDelete,
+    RoleAssign,
+}
+
+impl Permission {
+    // Permission を文字列として返すメソッド
+    pub fn to_str(&self) -> &str {
+        match self {
+            Permission::UserManagementRead => "USER_MANAGEMENT_READ",
+            Permission::UserManagementWrite => "USER_MANAGEMENT_WRITE",
+            Permission::UserDelete => "USER_DELETE",
+            Permission::RoleAssign => "ROLE_ASSIGN",
+        }
+    }
+
+    // 現在システムが知っているすべての権限
+    pub fn all() -> &'static [Permission] {
+        &[
+            Permission::UserManagementRead,
+            Permission::UserManagementWrite,
+            Permission::UserDelete,
+            Permission::RoleAssign,
+        ]
+    }
+}
+
+// 起動時に create_permissions 相当のシード処理へ渡す (name, description) の組
+pub const DEFAULT_PERMISSIONS: &[(&str, &str)] = &[
+    ("USER_MANAGEMENT_READ", "ユーザー情報の閲覧を許可する"),
+    ("USER_MANAGEMENT_WRITE", "ユーザー情報の更新を許可する"),
+    ("USER_DELETE", "ユーザーの削除を許可する"),
+    ("ROLE_ASSIGN", "ユーザーへのロール割り当てを許可する"),
+];
+
+// UserRole (Admin/User) と1対1で対応するデフォルトロールの固定ID
+// シード時に毎回同じUUIDを払い出すことで、RolePermissionをロール名を介さず直接引けるようにする
+pub const ADMIN_ROLE_ID: uuid::Uuid = uuid::Uuid::from_u128(0x0000_0000_0000_0000_0000_0000_0000_0001);
+pub const USER_ROLE_ID: uuid::Uuid = uuid::Uuid::from_u128(0x0000_0000_0000_0000_0000_0000_0000_0002);
+
+// 名前付きの権限集合を持つロール
+#[derive(Debug, Deserialize, Serialize, sqlx::FromRow, Clone)]
+pub struct Role {
+    pub id: uuid::Uuid,
+    pub name: String,
+    pub description: String,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl Role {
+    // 全権限を持つデフォルトの admin ロールを構築するヘルパー（起動時のシード処理で使用）
+    pub fn default_admin() -> Self {
+        Role {
+            id: ADMIN_ROLE_ID,
+            name: "admin".to_owned(),
+            description: "すべての権限を持つデフォルトの管理者ロール".to_owned(),
+            created_at: None,
+            updated_at: None,
+        }
+    }
+
+    // 個別の権限を持たないデフォルトの user ロールを構築するヘルパー（起動時のシード処理で使用）
+    pub fn default_user() -> Self {
+        Role {
+            id: USER_ROLE_ID,
+            name: "user".to_owned(),
+            description: "権限を個別に付与されるまでは何も持たないデフォルトの一般ユーザーロール".to_owned(),
+            created_at: None,
+            updated_at: None,
+        }
+    }
+
+    // UserRole に対応するロールのIDを返す
+    fn id_for(role: UserRole) -> uuid::Uuid {
+        match role {
+            UserRole::Admin => ADMIN_ROLE_ID,
+            UserRole::User => USER_ROLE_ID,
+        }
+    }
+}
+
+// ロールと権限の多対多マッピング（ロールが保有する権限を1行ずつ表す中間テーブル相当）
+#[derive(Debug, Deserialize, Serialize, sqlx::FromRow, Clone)]
+pub struct RolePermission {
+    pub role_id: uuid::Uuid,
+    pub permission: Permission,
+}
+
+// 起動時に admin ロールへ全権限を割り当てるシード用の RolePermission を構築する
+pub fn default_admin_role_permissions() -> Vec<RolePermission> {
+    Permission::all()
+        .iter()
+        .map(|&permission| RolePermission { role_id: ADMIN_ROLE_ID, permission })
+        .collect()
+}
+
+// 指定したユーザーが指定した権限を持っているかどうかを、ユーザーのロールに紐づく
+// RolePermission の一覧（DBの role_permissions テーブルを fetch_role_permissions 等で
+// 取得したものを渡す想定）から判定するヘルパー。admin ロールは全権限を持つため、一覧に関係なく常にtrue
+pub fn has_permission(user: &User, permission: Permission, role_permissions: &[RolePermission]) -> bool {
+    if user.role == UserRole::Admin {
+        return true;
+    }
+    let role_id = Role::id_for(user.role);
+    role_permissions
+        .iter()
+        .any(|rp| rp.role_id == role_id && rp.permission == permission)
+}
+
+// 対応しているパスワードハッシュアルゴリズム。Configの PASSWORD_HASH_ALGORITHM から選択される
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum PasswordHashAlgorithm {
+    Argon2id,
+    Scrypt,
+}
+
+impl PasswordHashAlgorithm {
+    // 保存済みハッシュの先頭に付与する識別子（検証時にどのアルゴリズムで照合するか判定するために使う）
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            PasswordHashAlgorithm::Argon2id => "argon2id",
+            PasswordHashAlgorithm::Scrypt => "scrypt",
+        }
+    }
+}
+
+// scrypt のワークファクター (log_n, r, p)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScryptParams {
+    pub log_n: u8,
+    pub r: u32,
+    pub p: u32,
+}
+
+// Argon2id のワークファクター（メモリコストとイテレーション回数）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Argon2Params {
+    pub memory_cost_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+// 保存されたハッシュ文字列の先頭のアルゴリズム識別子から、アルゴリズムを判定する（未知の識別子ならNone）
+// 例: "$argon2id$v=19$m=19456,t=2,p=1$..." / "$scrypt$ln=15,r=8,p=1$..."
+pub fn detect_hash_algorithm(stored_hash: &str) -> Option<PasswordHashAlgorithm> {
+    let body = stored_hash.strip_prefix('$')?;
+    let identifier = body.split('$').next()?;
+    [PasswordHashAlgorithm::Argon2id, PasswordHashAlgorithm::Scrypt]
+        .into_iter()
+        .find(|algorithm| algorithm.prefix() == identifier)
+}
+
+// "ln=15,r=8,p=1" のようなパラメータ部分から scrypt のワークファクターを取り出す
+fn parse_scrypt_params(stored_hash: &str) -> Option<ScryptParams> {
+    let params_part = stored_hash.split('$').nth(2)?;
+    let mut log_n = None;
+    let mut r = None;
+    let mut p = None;
+    for entry in params_part.split(',') {
+        let (key, value) = entry.split_once('=')?;
+        match key {
+            "ln" => log_n = value.parse::<u8>().ok(),
+            "r" => r = value.parse::<u32>().ok(),
+            "p" => p = value.parse::<u32>().ok(),
+            _ => {}
+        }
+    }
+    Some(ScryptParams { log_n: log_n?, r: r?, p: p? })
+}
+
+// "m=19456,t=2,p=1" のようなパラメータ部分から Argon2id のワークファクターを取り出す
+fn parse_argon2_params(stored_hash: &str) -> Option<Argon2Params> {
+    let params_part = stored_hash.split('$').nth(3)?;
+    let mut memory_cost_kib = None;
+    let mut iterations = None;
+    let mut parallelism = None;
+    for entry in params_part.split(',') {
+        let (key, value) = entry.split_once('=')?;
+        match key {
+            "m" => memory_cost_kib = value.parse::<u32>().ok(),
+            "t" => iterations = value.parse::<u32>().ok(),
+            "p" => parallelism = value.parse::<u32>().ok(),
+            _ => {}
+        }
+    }
+    Some(Argon2Params {
+        memory_cost_kib: memory_cost_kib?,
+        iterations: iterations?,
+        parallelism: parallelism?,
+    })
+}
+
+// 現在Configで選択されているアルゴリズム・パラメータと比べて、保存済みハッシュが
+// 古い/弱いもので作られていないかを判定する。ログイン成功時にtrueなら再ハッシュ（rehash on login）の対象にする
+pub fn needs_rehash(
+    stored_hash: &str,
+    current_algorithm: PasswordHashAlgorithm,
+    current_scrypt_params: ScryptParams,
+    current_argon2_params: Argon2Params,
+) -> bool {
+    match detect_hash_algorithm(stored_hash) {
+        Some(PasswordHashAlgorithm::Scrypt) if current_algorithm == PasswordHashAlgorithm::Scrypt => {
+            parse_scrypt_params(stored_hash) != Some(current_scrypt_params)
+        }
+        Some(PasswordHashAlgorithm::Argon2id) if current_algorithm == PasswordHashAlgorithm::Argon2id => {
+            parse_argon2_params(stored_hash) != Some(current_argon2_params)
+        }
+        // アルゴリズムが現行設定と異なる、または識別できない場合は安全側に倒して再ハッシュする
+        _ => true,
+    }
+}
+
+// Argon2id用のパラメータを jsonwebtoken 同様、外部クレートの型に変換するヘルパー
+fn argon2_lib_params(params: Argon2Params) -> Result<Argon2LibParams, ErrorMessage> {
+    Argon2LibParams::new(params.memory_cost_kib, params.iterations, params.parallelism, None)
+        .map_err(|_| ErrorMessage::HashingError)
+}
+
+// scrypt用のパラメータを外部クレートの型に変換するヘルパー
+fn scrypt_lib_params(params: ScryptParams) -> Result<ScryptLibParams, ErrorMessage> {
+    ScryptLibParams::new(params.log_n, params.r, params.p, ScryptLibParams::RECOMMENDED_LEN)
+        .map_err(|_| ErrorMessage::HashingError)
+}
+
+// 設定で選択されたアルゴリズム・パラメータでパスワードをハッシュ化し、PHC形式の文字列
+// （例: "$argon2id$v=19$m=19456,t=2,p=1$..."）を返す。detect_hash_algorithm/parse_*_params は
+// この形式を前提にパースしている
+pub fn hash_password(
+    password: &str,
+    algorithm: PasswordHashAlgorithm,
+    scrypt_params: ScryptParams,
+    argon2_params: Argon2Params,
+) -> Result<String, ErrorMessage> {
+    let salt = SaltString::generate(&mut OsRng);
+    match algorithm {
+        PasswordHashAlgorithm::Argon2id => {
+            let params = argon2_lib_params(argon2_params)?;
+            let hasher = Argon2::new(Argon2Algorithm::Argon2id, Argon2Version::V0x13, params);
+            hasher
+                .hash_password(password.as_bytes(), &salt)
+                .map(|hash| hash.to_string())
+                .map_err(|_| ErrorMessage::HashingError)
+        }
+        PasswordHashAlgorithm::Scrypt => {
+            let params = scrypt_lib_params(scrypt_params)?;
+            Scrypt
+                .hash_password_customized(password.as_bytes(), None, None, params, &salt)
+                .map(|hash| hash.to_string())
+                .map_err(|_| ErrorMessage::HashingError)
+        }
+    }
+}
+
+// 保存されたハッシュの識別子を見てアルゴリズムを判定し、そのアルゴリズムで照合する
+pub fn verify_password(password: &str, stored_hash: &str) -> Result<bool, ErrorMessage> {
+    let parsed_hash = PasswordHash::new(stored_hash).map_err(|_| ErrorMessage::UnsupportedHashFormat)?;
+    match detect_hash_algorithm(stored_hash) {
+        Some(PasswordHashAlgorithm::Argon2id) => {
+            Ok(Argon2::default().verify_password(password.as_bytes(), &parsed_hash).is_ok())
+        }
+        Some(PasswordHashAlgorithm::Scrypt) => {
+            Ok(Scrypt.verify_password(password.as_bytes(), &parsed_hash).is_ok())
+        }
+        None => Err(ErrorMessage::UnsupportedHashFormat),
+    }
+}
+
+// ログイン成功時に呼び出すヘルパー。パスワードを照合し、成功かつ現行パラメータより
+// 古い/弱いハッシュであれば現行パラメータで再ハッシュした値も合わせて返す（rehash on login）
+pub fn verify_password_and_rehash(
+    password: &str,
+    stored_hash: &str,
+    current_algorithm: PasswordHashAlgorithm,
+    current_scrypt_params: ScryptParams,
+    current_argon2_params: Argon2Params,
+) -> Result<(bool, Option<String>), ErrorMessage> {
+    if !verify_password(password, stored_hash)? {
+        return Ok((false, None));
+    }
+
+    if needs_rehash(stored_hash, current_algorithm, current_scrypt_params, current_argon2_params) {
+        let rehashed = hash_password(password, current_algorithm, current_scrypt_params, current_argon2_params)?;
+        Ok((true, Some(rehashed)))
+    } else {
+        Ok((true, None))
+    }
+}
+
+// サービス間・CI向けに発行する、限定的な権限を表すJWTのスコープ
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Scope {
+    UsersRead,
+    UsersWrite,
+    ProfileSelf,
+}
+
+impl Scope {
+    // JWTのscopesクレームに載せる文字列表現
+    pub fn to_str(&self) -> &str {
+        match self {
+            Scope::UsersRead => "users:read",
+            Scope::UsersWrite => "users:write",
+            Scope::ProfileSelf => "profile:self",
+        }
+    }
+}
+
+// JWTから読み出した文字列をScopeに変換できなかったことを表すエラー
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseScopeError;
+
+impl std::str::FromStr for Scope {
+    type Err = ParseScopeError;
+
+    // JWTから読み出した文字列をScopeに変換する（例: "users:read".parse::<Scope>()）
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "users:read" => Ok(Scope::UsersRead),
+            "users:write" => Ok(Scope::UsersWrite),
+            "profile:self" => Ok(Scope::ProfileSelf),
+            _ => Err(ParseScopeError),
+        }
+    }
+}
+
+// デコードされたJWTの中身を表す構造体（sub・scopes・有効期限）。
+// ルート側はこれを見て required scope を満たしているか判定する
+#[derive(Debug, Clone)]
+pub struct TokenInfo {
+    pub subject: String,
+    pub scopes: Vec<Scope>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl TokenInfo {
+    // トークンが指定したスコープを持っているかどうかを判定するヘルパー
+    pub fn has_scope(&self, required: Scope) -> bool {
+        self.scopes.contains(&required)
+    }
+}
+
 // ユーザー情報を表す構造体
 #[derive(Debug, Deserialize, Serialize, sqlx::FromRow, sqlx::Type, Clone)] // トレイトの自動実装
 pub struct User {
@@ -34,5 +388,293 @@ pub struct User {
     #[serde(rename = "createdAt")] // JSONシリアライズ時のキー名を "createdAt" に変更
     pub created_at: Option<DateTime<Utc>>, 
     #[serde(rename = "updatedAt")] // JSONシリアライズ時のキー名を "updatedAt" に変更
-    pub updated_at: Option<DateTime<Utc>>, 
+    pub updated_at: Option<DateTime<Utc>>,
+    // ユーザーが設定したパスワードヒント。未設定または空白のみの場合はNone
+    pub password_hint: Option<String>,
+    // アカウント削除リクエストの確認/取消に使うトークン。verification_tokenと同じパターンで
+    // 発行し、token_expires_atとは別に有効期限を持つ
+    pub deletion_token: Option<String>,
+    pub deletion_token_expires_at: Option<DateTime<Utc>>,
+}
+
+// 空白のみの値を None として扱いつつ、パスワードヒントの前後の空白を取り除く
+pub fn normalize_password_hint(hint: Option<String>) -> Option<String> {
+    hint.and_then(|value| {
+        let trimmed = value.trim();
+        if trimmed.is_empty() {
+            None
+        } else {
+            Some(trimmed.to_owned())
+        }
+    })
+}
+
+// ユーザーに紐づく認証情報の種類（1ユーザーが複数のCredentialを持てるようにするための区分）
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, sqlx::Type, PartialEq, Eq)]
+#[sqlx(type_name = "credential_type", rename_all = "snake_case")]
+pub enum CredentialType {
+    Password,
+    Email,
+    TotpSecret,
+    RecoveryCode,
+}
+
+impl CredentialType {
+    // CredentialType を文字列として返すメソッド（sqlxの rename_all = "snake_case" と同じ表現に揃える）
+    pub fn to_str(&self) -> &str {
+        match self {
+            CredentialType::Password => "password",
+            CredentialType::Email => "email",
+            CredentialType::TotpSecret => "totp_secret",
+            CredentialType::RecoveryCode => "recovery_code",
+        }
+    }
+}
+
+// ユーザーの認証情報を (user_id, credential_type) で一意に管理する構造体
+// User.email/password/verification_token の固定カラムとは別に、1ユーザーが複数のCredentialを持てるようにする
+#[derive(Debug, Deserialize, Serialize, sqlx::FromRow, Clone)]
+pub struct Credential {
+    pub id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+    pub credential_type: CredentialType,
+    pub credential: String,
+    pub validated: bool,
+    #[serde(rename = "timeCreated")]
+    pub time_created: Option<DateTime<Utc>>,
+    #[serde(rename = "lastUpdated")]
+    pub last_updated: Option<DateTime<Utc>>,
+}
+
+// Credentialテーブルへのアクセスをまとめる DAO 相当のヘルパー関数群
+
+// 複数のCredentialをまとめて挿入する
+pub async fn insert_credentials(
+    pool: &sqlx::PgPool,
+    user_id: uuid::Uuid,
+    credentials: &[(CredentialType, String)],
+) -> Result<(), sqlx::Error> {
+    for (credential_type, credential) in credentials {
+        sqlx::query(
+            r#"INSERT INTO credentials (user_id, credential_type, credential, validated)
+               VALUES ($1, $2, $3, false)"#,
+        )
+        .bind(user_id)
+        .bind(credential_type)
+        .bind(credential)
+        .execute(pool)
+        .await?;
+    }
+    Ok(())
+}
+
+// 指定したユーザーに紐づくCredentialを全件取得する
+pub async fn fetch_user_credentials(
+    pool: &sqlx::PgPool,
+    user_id: uuid::Uuid,
+) -> Result<Vec<Credential>, sqlx::Error> {
+    sqlx::query_as::<_, Credential>(
+        r#"SELECT id, user_id, credential_type, credential, validated, time_created, last_updated
+           FROM credentials WHERE user_id = $1"#,
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+}
+
+#[cfg(test)]
+mod has_permission_tests {
+    use super::*;
+
+    fn build_user(role: UserRole) -> User {
+        User {
+            id: uuid::Uuid::new_v4(),
+            name: "test".to_owned(),
+            email: "test@example.com".to_owned(),
+            password: "irrelevant".to_owned(),
+            role,
+            verified: true,
+            verification_token: None,
+            token_expires_at: None,
+            created_at: None,
+            updated_at: None,
+            password_hint: None,
+            deletion_token: None,
+            deletion_token_expires_at: None,
+        }
+    }
+
+    #[test]
+    fn admin_has_every_permission_even_with_no_role_permission_rows() {
+        let admin = build_user(UserRole::Admin);
+        for &permission in Permission::all() {
+            assert!(has_permission(&admin, permission, &[]));
+        }
+    }
+
+    #[test]
+    fn user_without_matching_role_permission_row_is_denied() {
+        let user = build_user(UserRole::User);
+        assert!(!has_permission(&user, Permission::UserDelete, &[]));
+    }
+
+    #[test]
+    fn user_is_granted_only_the_permissions_assigned_to_their_role() {
+        let user = build_user(UserRole::User);
+        let role_permissions = vec![RolePermission { role_id: USER_ROLE_ID, permission: Permission::UserManagementRead }];
+
+        assert!(has_permission(&user, Permission::UserManagementRead, &role_permissions));
+        assert!(!has_permission(&user, Permission::UserDelete, &role_permissions));
+    }
+
+    #[test]
+    fn role_permission_rows_for_a_different_role_do_not_leak_across_roles() {
+        let user = build_user(UserRole::User);
+        // admin ロール宛のRolePermissionしかない場合、userロールのユーザーには付与されない
+        let role_permissions = default_admin_role_permissions();
+
+        for &permission in Permission::all() {
+            assert!(!has_permission(&user, permission, &role_permissions));
+        }
+    }
+}
+
+#[cfg(test)]
+mod password_hash_tests {
+    use super::*;
+
+    fn scrypt_params() -> ScryptParams {
+        // テストが速く終わるよう、本番より大幅に軽いワークファクターにする
+        ScryptParams { log_n: 4, r: 8, p: 1 }
+    }
+
+    fn argon2_params() -> Argon2Params {
+        Argon2Params { memory_cost_kib: 8, iterations: 1, parallelism: 1 }
+    }
+
+    #[test]
+    fn argon2id_hash_round_trips_through_verify_password() {
+        let hash = hash_password("correct horse", PasswordHashAlgorithm::Argon2id, scrypt_params(), argon2_params())
+            .expect("hashing should succeed");
+
+        assert!(verify_password("correct horse", &hash).unwrap());
+        assert!(!verify_password("wrong password", &hash).unwrap());
+        assert_eq!(detect_hash_algorithm(&hash), Some(PasswordHashAlgorithm::Argon2id));
+    }
+
+    #[test]
+    fn scrypt_hash_round_trips_through_verify_password() {
+        let hash = hash_password("correct horse", PasswordHashAlgorithm::Scrypt, scrypt_params(), argon2_params())
+            .expect("hashing should succeed");
+
+        assert!(verify_password("correct horse", &hash).unwrap());
+        assert!(!verify_password("wrong password", &hash).unwrap());
+        assert_eq!(detect_hash_algorithm(&hash), Some(PasswordHashAlgorithm::Scrypt));
+    }
+
+    #[test]
+    fn verify_password_rejects_malformed_hash() {
+        assert_eq!(verify_password("anything", "not-a-phc-hash"), Err(ErrorMessage::UnsupportedHashFormat));
+    }
+
+    #[test]
+    fn detect_hash_algorithm_returns_none_for_unknown_identifier() {
+        assert_eq!(detect_hash_algorithm("$bcrypt$v=1$..."), None);
+        assert_eq!(detect_hash_algorithm("not-a-hash-at-all"), None);
+    }
+
+    #[test]
+    fn needs_rehash_is_false_when_params_match_current_config() {
+        let params = scrypt_params();
+        let hash = hash_password("hunter2", PasswordHashAlgorithm::Scrypt, params, argon2_params()).unwrap();
+
+        assert!(!needs_rehash(&hash, PasswordHashAlgorithm::Scrypt, params, argon2_params()));
+    }
+
+    #[test]
+    fn needs_rehash_is_true_when_work_factor_increased() {
+        let old_params = scrypt_params();
+        let hash = hash_password("hunter2", PasswordHashAlgorithm::Scrypt, old_params, argon2_params()).unwrap();
+
+        let stronger_params = ScryptParams { log_n: old_params.log_n + 1, ..old_params };
+        assert!(needs_rehash(&hash, PasswordHashAlgorithm::Scrypt, stronger_params, argon2_params()));
+    }
+
+    #[test]
+    fn needs_rehash_is_true_when_algorithm_changed() {
+        let hash = hash_password("hunter2", PasswordHashAlgorithm::Argon2id, scrypt_params(), argon2_params()).unwrap();
+
+        assert!(needs_rehash(&hash, PasswordHashAlgorithm::Scrypt, scrypt_params(), argon2_params()));
+    }
+
+    #[test]
+    fn verify_password_and_rehash_returns_new_hash_only_when_params_are_stale() {
+        let old_params = scrypt_params();
+        let hash = hash_password("hunter2", PasswordHashAlgorithm::Scrypt, old_params, argon2_params()).unwrap();
+
+        let (valid, rehashed) =
+            verify_password_and_rehash("hunter2", &hash, PasswordHashAlgorithm::Scrypt, old_params, argon2_params())
+                .unwrap();
+        assert!(valid);
+        assert!(rehashed.is_none());
+
+        let stronger_params = ScryptParams { log_n: old_params.log_n + 1, ..old_params };
+        let (valid, rehashed) = verify_password_and_rehash(
+            "hunter2",
+            &hash,
+            PasswordHashAlgorithm::Scrypt,
+            stronger_params,
+            argon2_params(),
+        )
+        .unwrap();
+        assert!(valid);
+        let rehashed = rehashed.expect("stale work factor should trigger a rehash");
+        assert!(verify_password("hunter2", &rehashed).unwrap());
+    }
+
+    #[test]
+    fn verify_password_and_rehash_does_not_rehash_on_wrong_password() {
+        let hash = hash_password("hunter2", PasswordHashAlgorithm::Scrypt, scrypt_params(), argon2_params()).unwrap();
+
+        let (valid, rehashed) = verify_password_and_rehash(
+            "not hunter2",
+            &hash,
+            PasswordHashAlgorithm::Scrypt,
+            scrypt_params(),
+            argon2_params(),
+        )
+        .unwrap();
+        assert!(!valid);
+        assert!(rehashed.is_none());
+    }
+}
+
+#[cfg(test)]
+mod password_hint_tests {
+    use super::*;
+
+    #[test]
+    fn none_stays_none() {
+        assert_eq!(normalize_password_hint(None), None);
+    }
+
+    #[test]
+    fn empty_string_becomes_none() {
+        assert_eq!(normalize_password_hint(Some(String::new())), None);
+    }
+
+    #[test]
+    fn whitespace_only_becomes_none() {
+        assert_eq!(normalize_password_hint(Some("   \t\n  ".to_owned())), None);
+    }
+
+    #[test]
+    fn surrounding_whitespace_is_trimmed() {
+        assert_eq!(normalize_password_hint(Some("  my first pet  ".to_owned())), Some("my first pet".to_owned()));
+    }
+
+    #[test]
+    fn internal_whitespace_is_preserved() {
+        assert_eq!(normalize_password_hint(Some(" my  pet ".to_owned())), Some("my  pet".to_owned()));
+    }
 }
\ No newline at end of file